@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use crate::cdsl::settings::PredicateNode;
+use crate::cdsl::types::ValueType;
+use crate::cdsl::xform::TransformGroupIndex;
+
+/// The legalization selected for a single controlling type.
+///
+/// Most types only ever need one transform group, but a type can also be
+/// legalized conditionally: `predicate` (when present) is checked at
+/// runtime, and `group` or `group_when_false` is used depending on the
+/// result. This lets a CPU mode keep a feature-gated fast path (e.g. an
+/// expansion built around a hardware instruction) alongside a portable
+/// fallback for when the required ISA feature isn't present, instead of
+/// forcing every caller onto one fixed expansion.
+pub struct Legalization {
+    pub group: TransformGroupIndex,
+    pub group_when_false: Option<TransformGroupIndex>,
+    pub predicate: Option<Box<dyn PredicateNode>>,
+}
+
+pub struct CpuMode {
+    pub name: &'static str,
+    pub default_legalize: Option<TransformGroupIndex>,
+    pub monomorphic_legalize: Option<TransformGroupIndex>,
+    pub legalize_types: HashMap<ValueType, Legalization>,
+    pub big_endian: bool,
+}
+
+impl CpuMode {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            default_legalize: None,
+            monomorphic_legalize: None,
+            legalize_types: HashMap::new(),
+            big_endian: false,
+        }
+    }
+
+    /// Mark this CPU mode as big-endian. Defaults to little-endian.
+    pub fn set_big_endian(&mut self, big_endian: bool) {
+        self.big_endian = big_endian;
+    }
+
+    pub fn legalize_monomorphic(&mut self, group: TransformGroupIndex) {
+        self.monomorphic_legalize = Some(group);
+    }
+
+    pub fn legalize_default(&mut self, group: TransformGroupIndex) {
+        self.default_legalize = Some(group);
+    }
+
+    /// Unconditionally select `group` to legalize instructions controlled by `ty`.
+    pub fn legalize_type(&mut self, ty: impl Into<ValueType>, group: TransformGroupIndex) {
+        self.legalize_types.insert(
+            ty.into(),
+            Legalization {
+                group,
+                group_when_false: None,
+                predicate: None,
+            },
+        );
+    }
+
+    /// Select between two legalization groups for `ty` depending on whether
+    /// `predicate` holds at runtime: `group_when_true` when it does, and
+    /// `group_when_false` otherwise.
+    pub fn legalize_type_if(
+        &mut self,
+        ty: impl Into<ValueType>,
+        predicate: impl PredicateNode + 'static,
+        group_when_true: TransformGroupIndex,
+        group_when_false: TransformGroupIndex,
+    ) {
+        self.legalize_types.insert(
+            ty.into(),
+            Legalization {
+                group: group_when_true,
+                group_when_false: Some(group_when_false),
+                predicate: Some(Box::new(predicate)),
+            },
+        );
+    }
+}
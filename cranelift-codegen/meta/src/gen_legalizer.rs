@@ -0,0 +1,104 @@
+//! Generate the legalizer transforms, dispatched by controlling type (and,
+//! where a CPU mode asks for it, by an ISA-flag predicate on top of that).
+
+use crate::cdsl::formats::FormatRegistry;
+use crate::cdsl::isa::TargetIsa;
+use crate::cdsl::xform::TransformGroups;
+use crate::error;
+use crate::srcgen::Formatter;
+
+fn gen_isa(
+    isa: &TargetIsa,
+    format_registry: &FormatRegistry,
+    transform_groups: &TransformGroups,
+    fn_name: &str,
+    fmt: &mut Formatter,
+) {
+    for cpu_mode in &isa.cpu_modes {
+        // Namespace by ISA, not just by CPU mode name: mode names like "I64"
+        // are reused across ISAs (x86_64 and s390x both have one), and every
+        // ISA's functions land in the same generated `new_legalize.rs`.
+        fmt.line(&format!(
+            "fn {}_{}_{}(pos: &mut Cursor, inst: Inst) -> bool {{",
+            fn_name, isa.name, cpu_mode.name
+        ));
+        fmt.indent(|fmt| {
+            fmt.line("let ctrl_typevar = pos.func.dfg.ctrl_typevar(inst);");
+
+            // Instructions with no controlling typevar (e.g. most control-flow
+            // and memory ops) never match an entry in `legalize_types` below,
+            // so they must be dispatched to `monomorphic_legalize` up front
+            // rather than falling through to `default_legalize`.
+            fmt.line("if ctrl_typevar.is_invalid() {");
+            fmt.indent(|fmt| {
+                if let Some(monomorphic) = cpu_mode.monomorphic_legalize {
+                    let group = transform_groups.get(monomorphic);
+                    fmt.line(&format!("return {}(pos, inst);", group.rust_fn_name()));
+                } else {
+                    fmt.line("return false;");
+                }
+            });
+            fmt.line("}");
+
+            // `legalize_types` is a `HashMap`, whose iteration order isn't
+            // stable across runs; sort by the generated type name so the
+            // emitted match arms (and thus the generated source) are
+            // reproducible from one `cargo build` to the next.
+            let mut legalize_types: Vec<_> = cpu_mode.legalize_types.iter().collect();
+            legalize_types.sort_by_key(|(ty, _)| ty.rust_name());
+
+            fmt.line("match ctrl_typevar {");
+            fmt.indent(|fmt| {
+                for (ty, legalization) in legalize_types {
+                    let group = transform_groups.get(legalization.group);
+                    fmt.line(&format!("_ if ctrl_typevar == {} => {{", ty.rust_name()));
+                    fmt.indent(|fmt| match (&legalization.predicate, &legalization.group_when_false) {
+                        (Some(predicate), Some(group_when_false)) => {
+                            let group_when_false = transform_groups.get(*group_when_false);
+                            fmt.line(&format!("if {} {{", predicate.rust_predicate()));
+                            fmt.indent(|fmt| {
+                                fmt.line(&format!("{}(pos, inst)", group.rust_fn_name()));
+                            });
+                            fmt.line("} else {");
+                            fmt.indent(|fmt| {
+                                fmt.line(&format!("{}(pos, inst)", group_when_false.rust_fn_name()));
+                            });
+                            fmt.line("}");
+                        }
+                        _ => {
+                            fmt.line(&format!("{}(pos, inst)", group.rust_fn_name()));
+                        }
+                    });
+                    fmt.line("}");
+                }
+                if let Some(default) = cpu_mode.default_legalize {
+                    let group = transform_groups.get(default);
+                    fmt.line(&format!("_ => {}(pos, inst),", group.rust_fn_name()));
+                } else {
+                    fmt.line("_ => false,");
+                }
+            });
+            fmt.line("}");
+        });
+        fmt.line("}");
+        fmt.empty_line();
+    }
+    let _ = format_registry;
+}
+
+pub fn generate(
+    isas: &[TargetIsa],
+    format_registry: &FormatRegistry,
+    transform_groups: &TransformGroups,
+    fn_name: &str,
+    out_dir: &str,
+) -> Result<(), error::Error> {
+    let mut fmt = Formatter::new();
+
+    for isa in isas {
+        gen_isa(isa, format_registry, transform_groups, fn_name, &mut fmt);
+    }
+
+    fmt.update_file(&format!("{}.rs", fn_name), out_dir)?;
+    Ok(())
+}
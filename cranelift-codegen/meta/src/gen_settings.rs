@@ -0,0 +1,173 @@
+//! Generate the setting enums, the `Flags` accessors and the detection table
+//! for a `SettingGroup`. The `Flags` struct itself is not generated here.
+
+use crate::cdsl::settings::{Predicate, Setting, SettingGroup};
+use crate::error;
+use crate::srcgen::Formatter;
+
+/// Which parent group (if any) a generated settings group inherits shared
+/// settings from.
+pub enum ParentGroup {
+    None,
+    Shared,
+}
+
+fn gen_enum_types(group: &SettingGroup, fmt: &mut Formatter) {
+    for setting in &group.settings {
+        if let Setting::Enum(enum_setting) = setting {
+            fmt.line(&format!("/// Values for `{}.{}`.", group.name, enum_setting.name));
+            fmt.line("#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]");
+            fmt.line(&format!("pub enum {} {{", enum_setting.rust_type_name()));
+            fmt.indent(|fmt| {
+                for value in &enum_setting.values {
+                    fmt.line(&format!("{},", value));
+                }
+            });
+            fmt.line("}");
+        }
+    }
+}
+
+fn gen_getter(setting: &Setting, fmt: &mut Formatter) {
+    match setting {
+        Setting::Bool(bool_setting) => {
+            fmt.doc_comment(&bool_setting.comment);
+            fmt.line(&format!("pub fn {}(&self) -> bool {{", bool_setting.name));
+            fmt.indent(|fmt| {
+                fmt.line(&format!(
+                    "self.numbered_predicate({})",
+                    bool_setting.predicate_number
+                ));
+            });
+            fmt.line("}");
+        }
+        Setting::Enum(enum_setting) => {
+            fmt.doc_comment(&enum_setting.comment);
+            fmt.line(&format!(
+                "pub fn {}(&self) -> {} {{",
+                enum_setting.name,
+                enum_setting.rust_type_name()
+            ));
+            fmt.indent(|fmt| {
+                fmt.line(&format!("self.{}_raw().into()", enum_setting.name));
+            });
+            fmt.line("}");
+        }
+        Setting::Num(num_setting) => {
+            fmt.doc_comment(&num_setting.comment);
+            fmt.line(&format!("pub fn {}(&self) -> u8 {{", num_setting.name));
+            fmt.indent(|fmt| {
+                fmt.line(&format!("self.bytes[{}]", num_setting.byte_offset));
+            });
+            fmt.line("}");
+        }
+    }
+}
+
+fn gen_predicates(group: &SettingGroup, fmt: &mut Formatter) {
+    for predicate in &group.predicates {
+        fmt.doc_comment(&format!("Computed predicate `{}`.", predicate.name));
+        fmt.line(&format!("pub fn {}(&self) -> bool {{", predicate.name));
+        fmt.indent(|fmt| {
+            fmt.line(&format!("self.numbered_predicate({})", predicate.number));
+        });
+        fmt.line("}");
+    }
+}
+
+/// Emit a machine-readable link from each boolean setting's name to the bit
+/// position the generator assigned it, so that out-of-tree detection code
+/// (e.g. `cranelift-native`) doesn't need to hardcode offsets. CPUID (or any
+/// other) detection code can use this table to flip exactly the settings it
+/// found evidence for, and let preset predicates fall out of the boolean
+/// values as usual.
+fn gen_detect_table(group: &SettingGroup, fmt: &mut Formatter) {
+    fmt.doc_comment(&format!(
+        "Name-to-bit-offset table for the boolean settings in `{}`, for use by hardware \
+         feature detection.",
+        group.name
+    ));
+    fmt.line("pub static DETECT_TABLE: &[(&str, u8, u8)] = &[");
+    fmt.indent(|fmt| {
+        for setting in &group.settings {
+            if let Setting::Bool(bool_setting) = setting {
+                fmt.line(&format!(
+                    "(\"{}\", {}, {}),",
+                    bool_setting.name, bool_setting.byte_offset, bool_setting.bit_offset
+                ));
+            }
+        }
+    });
+    fmt.line("];");
+    fmt.empty_line();
+
+    fmt.doc_comment(
+        "Apply a list of `(setting name, detected)` pairs to `flags`, flipping exactly the \
+         booleans named in `DETECT_TABLE` and leaving everything else (including derived \
+         presets) to fall out naturally.",
+    );
+    fmt.line("pub fn detect(flags: &mut super::Flags, detected: &[(&str, bool)]) {");
+    fmt.indent(|fmt| {
+        fmt.line("for &(name, is_present) in detected {");
+        fmt.indent(|fmt| {
+            fmt.line("if let Some(&(_, byte_offset, bit_offset)) =");
+            fmt.indent(|fmt| {
+                fmt.line("DETECT_TABLE.iter().find(|(setting_name, _, _)| *setting_name == name)");
+            });
+            fmt.line("{");
+            fmt.indent(|fmt| {
+                fmt.line("if is_present {");
+                fmt.indent(|fmt| {
+                    fmt.line("flags.bytes[byte_offset as usize] |= 1 << bit_offset;");
+                });
+                fmt.line("} else {");
+                fmt.indent(|fmt| {
+                    fmt.line("flags.bytes[byte_offset as usize] &= !(1 << bit_offset);");
+                });
+                fmt.line("}");
+            });
+            fmt.line("}");
+        });
+        fmt.line("}");
+    });
+    fmt.line("}");
+}
+
+/// Generate the settings group and write the generated Rust source to
+/// `<out_dir>/<filename>`.
+pub fn generate(
+    group: &SettingGroup,
+    parent: ParentGroup,
+    filename: &str,
+    out_dir: &str,
+) -> Result<(), error::Error> {
+    let mut fmt = Formatter::new();
+
+    gen_enum_types(group, &mut fmt);
+
+    fmt.line("impl Flags {");
+    fmt.indent(|fmt| {
+        match parent {
+            ParentGroup::None => {}
+            ParentGroup::Shared => {
+                fmt.doc_comment("Returns the shared flags.");
+                fmt.line("pub fn shared_flags(&self) -> &shared::Flags {");
+                fmt.indent(|fmt| fmt.line("&self.shared"));
+                fmt.line("}");
+            }
+        }
+
+        for setting in &group.settings {
+            gen_getter(setting, fmt);
+        }
+
+        gen_predicates(group, fmt);
+    });
+    fmt.line("}");
+    fmt.empty_line();
+
+    gen_detect_table(group, &mut fmt);
+
+    fmt.update_file(filename, out_dir)?;
+    Ok(())
+}
@@ -0,0 +1,8 @@
+use crate::cdsl::formats::FormatRegistry;
+use crate::cdsl::instructions::{InstructionGroup, InstructionGroupBuilder};
+
+pub fn define(_format_registry: &FormatRegistry) -> InstructionGroup {
+    // s390x has no instructions of its own yet: it lowers directly from the
+    // shared instruction set, the same way x86 layers its extras on top.
+    InstructionGroupBuilder::new("s390x").finish()
+}
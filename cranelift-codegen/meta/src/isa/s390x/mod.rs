@@ -0,0 +1,113 @@
+use crate::cdsl::cpu_modes::CpuMode;
+use crate::cdsl::isa::TargetIsa;
+use crate::cdsl::regs::{IsaRegs, IsaRegsBuilder, RegBankBuilder, RegClassBuilder};
+use crate::cdsl::settings::{PredicateNode, SettingGroup, SettingGroupBuilder};
+
+use crate::shared::types::Bool::B1;
+use crate::shared::types::Float::{F32, F64};
+use crate::shared::types::Int::{I16, I32, I64, I8};
+use crate::shared::Definitions as SharedDefinitions;
+
+mod instructions;
+
+fn define_settings(_shared: &SettingGroup) -> SettingGroup {
+    let mut settings = SettingGroupBuilder::new("s390x");
+
+    // Facilities available on the z14 baseline, the oldest machine we support.
+    let has_vector = settings.add_bool(
+        "has_vector",
+        "Vector facility, available since the z13",
+        true,
+    );
+    let has_mie2 = settings.add_bool(
+        "has_mie2",
+        "Miscellaneous-Instruction-Extensions Facility 2, available since the z14",
+        true,
+    );
+
+    // Facilities that extend the z14 baseline on the arch13 (z15) generation.
+    let has_vxrs_ext2 = settings.add_bool(
+        "has_vxrs_ext2",
+        "Vector-Enhancements Facility 2, available since the z15",
+        false,
+    );
+    let has_mie3 = settings.add_bool(
+        "has_mie3",
+        "Miscellaneous-Instruction-Extensions Facility 3, available since the z15",
+        false,
+    );
+
+    settings.add_predicate("use_vxrs_ext2", predicate!(has_vxrs_ext2));
+    settings.add_predicate("use_mie3", predicate!(has_mie3));
+
+    let z14 = settings.add_preset("z14", preset!(has_vector && has_mie2));
+    let arch13 = settings.add_preset("arch13", preset!(z14 && has_vxrs_ext2 && has_mie3));
+    // z15 is the marketing name for the arch13 architecture level.
+    settings.add_preset("z15", preset!(arch13));
+
+    settings.finish()
+}
+
+fn define_registers() -> IsaRegs {
+    let mut regs = IsaRegsBuilder::new();
+
+    let builder = RegBankBuilder::new("IntRegs", "r").units(16).track_pressure(true);
+    let int_regs = regs.add_bank(builder);
+
+    let builder = RegBankBuilder::new("FloatRegs", "f").units(16).track_pressure(true);
+    let float_regs = regs.add_bank(builder);
+
+    let builder = RegBankBuilder::new("VectorRegs", "v").units(32).track_pressure(true);
+    let vector_regs = regs.add_bank(builder);
+
+    let builder = RegBankBuilder::new("FlagRegs", "")
+        .units(1)
+        .names(vec!["cc"])
+        .track_pressure(false);
+    let flag_reg = regs.add_bank(builder);
+
+    let builder = RegClassBuilder::new_toplevel("GPR", int_regs);
+    regs.add_class(builder);
+
+    let builder = RegClassBuilder::new_toplevel("FPR", float_regs);
+    regs.add_class(builder);
+
+    let builder = RegClassBuilder::new_toplevel("VR", vector_regs);
+    regs.add_class(builder);
+
+    let builder = RegClassBuilder::new_toplevel("FLAG", flag_reg);
+    regs.add_class(builder);
+
+    regs.finish()
+}
+
+pub fn define(shared_defs: &mut SharedDefinitions) -> TargetIsa {
+    let settings = define_settings(&shared_defs.settings);
+    let regs = define_registers();
+
+    let inst_group = instructions::define(&shared_defs.format_registry);
+
+    // s390x is a single, big-endian CPU mode: there is no separate 32-bit mode
+    // to model, unlike x86.
+    let mut s390x = CpuMode::new("I64");
+    s390x.set_big_endian(true);
+
+    let expand_flags = shared_defs.transform_groups.by_name("expand_flags");
+    let narrow = shared_defs.transform_groups.by_name("narrow");
+    let widen = shared_defs.transform_groups.by_name("widen");
+    let expand = shared_defs.transform_groups.by_name("expand");
+
+    s390x.legalize_monomorphic(expand_flags);
+    s390x.legalize_default(narrow);
+    s390x.legalize_type(B1, expand_flags);
+    s390x.legalize_type(I8, widen);
+    s390x.legalize_type(I16, widen);
+    s390x.legalize_type(I32, expand);
+    s390x.legalize_type(I64, expand);
+    s390x.legalize_type(F32, expand);
+    s390x.legalize_type(F64, expand);
+
+    let cpu_modes = vec![s390x];
+
+    TargetIsa::new("s390x", inst_group, settings, regs, cpu_modes)
+}
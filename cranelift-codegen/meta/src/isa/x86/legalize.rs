@@ -0,0 +1,122 @@
+//! x86-specific legalizations.
+//!
+//! The shared `widen` group legalizes `i8`/`i16` instructions by simply
+//! zero/sign-extending the operands up to `i32`, running the 32-bit
+//! instruction, and truncating the result back down. That's correct for
+//! most arithmetic, but `popcnt`, `rotl` and `rotr` all depend on the
+//! operand *width* in ways the naive widen-and-truncate gets wrong:
+//! widening `popcnt` counts bits set in the zero/sign-extended padding, and
+//! widening a rotate changes how far bits wrap around. This module adds
+//! width-correct expansions for those three instructions on `i8`/`i16` and
+//! falls back to `widen` for everything else.
+
+use crate::cdsl::instructions::InstructionGroup;
+use crate::cdsl::xform::TransformGroupBuilder;
+
+use crate::shared::Definitions as SharedDefinitions;
+
+pub fn define(shared_defs: &mut SharedDefinitions, _inst_group: &InstructionGroup) {
+    let insts = &shared_defs.instructions;
+    let widen = shared_defs.transform_groups.by_name("widen");
+
+    let mut x86_narrow = TransformGroupBuilder::new(
+        "x86_narrow",
+        r#"
+        x86-specific narrow-integer legalizations.
+
+        Handles `popcnt`, `rotl` and `rotr` on `i8`/`i16` directly, since
+        naively widening them to `i32` (as the shared `widen` group does for
+        every other instruction) produces the wrong answer. Everything else
+        falls through to `widen`.
+        "#,
+    )
+    .chain_with(widen);
+
+    let popcnt = insts.by_name("popcnt");
+    let uextend = insts.by_name("uextend");
+    let ireduce = insts.by_name("ireduce");
+    let rotl = insts.by_name("rotl");
+    let rotr = insts.by_name("rotr");
+    let band_imm = insts.by_name("band_imm");
+    let irsub_imm = insts.by_name("irsub_imm");
+    let ishl = insts.by_name("ishl");
+    let ushr = insts.by_name("ushr");
+    let bor = insts.by_name("bor");
+
+    // popcnt(x:iN) -> ireduce(popcnt(uextend.i32(x))). Zero-extending means
+    // the padding bits are all 0 and so never contribute to the count;
+    // `ireduce` then truncates the (correct) i32 count back down to iN
+    // without losing information, since a bit count in `0..=N` always fits.
+    x86_narrow.legalize(
+        def!(y = popcnt.i8(x)),
+        vec![
+            def!(xe = uextend.i32(x)),
+            def!(ye = popcnt(xe)),
+            def!(y = ireduce.i8(ye)),
+        ],
+    );
+    x86_narrow.legalize(
+        def!(y = popcnt.i16(x)),
+        vec![
+            def!(xe = uextend.i32(x)),
+            def!(ye = popcnt(xe)),
+            def!(y = ireduce.i16(ye)),
+        ],
+    );
+
+    // rotl(x:iN, y) = (x << (y & (N-1))) | ushr(x, (N - (y & (N-1))) & (N-1)).
+    // Masking the shift amount first keeps both shifts in range (an
+    // unmasked `x >> N` is undefined); computing the complementary shift as
+    // `N - (y & (N-1))`, rather than assuming a fixed 32-bit width as the
+    // widen path effectively does, keeps the wraparound width-correct. When
+    // `y & (N-1) == 0` the `ushr` amount masks back down to 0, so both
+    // halves are `x` itself and the result is `x` unchanged, avoiding the
+    // undefined `x >> N` shift. `rotr` is the mirror image:
+    // shift right by the masked amount, left by its complement.
+    x86_narrow.legalize(
+        def!(a = rotl.i8(x, y)),
+        vec![
+            def!(amt = band_imm(y, 7)),
+            def!(inv = irsub_imm(amt, 8)),
+            def!(inv_amt = band_imm(inv, 7)),
+            def!(lo = ishl(x, amt)),
+            def!(hi = ushr(x, inv_amt)),
+            def!(a = bor(lo, hi)),
+        ],
+    );
+    x86_narrow.legalize(
+        def!(a = rotl.i16(x, y)),
+        vec![
+            def!(amt = band_imm(y, 15)),
+            def!(inv = irsub_imm(amt, 16)),
+            def!(inv_amt = band_imm(inv, 15)),
+            def!(lo = ishl(x, amt)),
+            def!(hi = ushr(x, inv_amt)),
+            def!(a = bor(lo, hi)),
+        ],
+    );
+    x86_narrow.legalize(
+        def!(a = rotr.i8(x, y)),
+        vec![
+            def!(amt = band_imm(y, 7)),
+            def!(inv = irsub_imm(amt, 8)),
+            def!(inv_amt = band_imm(inv, 7)),
+            def!(lo = ushr(x, amt)),
+            def!(hi = ishl(x, inv_amt)),
+            def!(a = bor(lo, hi)),
+        ],
+    );
+    x86_narrow.legalize(
+        def!(a = rotr.i16(x, y)),
+        vec![
+            def!(amt = band_imm(y, 15)),
+            def!(inv = irsub_imm(amt, 16)),
+            def!(inv_amt = band_imm(inv, 15)),
+            def!(lo = ushr(x, amt)),
+            def!(hi = ishl(x, inv_amt)),
+            def!(a = bor(lo, hi)),
+        ],
+    );
+
+    x86_narrow.build(&mut shared_defs.transform_groups);
+}
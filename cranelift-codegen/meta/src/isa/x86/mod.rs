@@ -20,7 +20,33 @@ fn define_settings(_shared: &SettingGroup) -> SettingGroup {
     let has_sse41 = settings.add_bool("has_sse41", "SSE4.1: CPUID.01H:ECX.SSE4_1[bit 19]", false);
     let has_sse42 = settings.add_bool("has_sse42", "SSE4.2: CPUID.01H:ECX.SSE4_2[bit 20]", false);
     let has_popcnt = settings.add_bool("has_popcnt", "POPCNT: CPUID.01H:ECX.POPCNT[bit 23]", false);
-    settings.add_bool("has_avx", "AVX: CPUID.01H:ECX.AVX[bit 28]", false);
+    let has_avx = settings.add_bool("has_avx", "AVX: CPUID.01H:ECX.AVX[bit 28]", false);
+    // AVX2 is reported in CPUID.07H:EBX, not 01H:ECX; it is listed here beside AVX.
+    let has_avx2 = settings.add_bool("has_avx2", "AVX2: CPUID.07H:EBX.AVX2[bit 5]", false);
+    let has_fma = settings.add_bool("has_fma", "FMA: CPUID.01H:ECX.FMA[bit 12]", false);
+    let has_f16c = settings.add_bool("has_f16c", "F16C: CPUID.01H:ECX.F16C[bit 29]", false);
+
+    // CPUID.07H:EBX (AVX-512 family)
+    let has_avx512f = settings.add_bool(
+        "has_avx512f",
+        "AVX512F: CPUID.07H:EBX.AVX512F[bit 16]",
+        false,
+    );
+    let has_avx512vl = settings.add_bool(
+        "has_avx512vl",
+        "AVX512VL: CPUID.07H:EBX.AVX512VL[bit 31]",
+        false,
+    );
+    let has_avx512dq = settings.add_bool(
+        "has_avx512dq",
+        "AVX512DQ: CPUID.07H:EBX.AVX512DQ[bit 17]",
+        false,
+    );
+    let has_avx512bw = settings.add_bool(
+        "has_avx512bw",
+        "AVX512BW: CPUID.07H:EBX.AVX512BW[bit 30]",
+        false,
+    );
 
     // CPUID.(EAX=07H, ECX=0H):EBX
     let has_bmi1 = settings.add_bool(
@@ -46,6 +72,11 @@ fn define_settings(_shared: &SettingGroup) -> SettingGroup {
     settings.add_predicate("use_popcnt", predicate!(has_popcnt && has_sse42));
     settings.add_predicate("use_bmi1", predicate!(has_bmi1));
     settings.add_predicate("use_lznct", predicate!(has_lzcnt));
+    settings.add_predicate("use_avx2", predicate!(has_avx && has_avx2));
+    settings.add_predicate("use_fma", predicate!(has_avx && has_fma));
+    settings.add_predicate("use_avx512vl", predicate!(has_avx512f && has_avx512vl));
+    settings.add_predicate("use_avx512dq", predicate!(has_avx512f && has_avx512dq));
+    settings.add_predicate("use_avx512bw", predicate!(has_avx512f && has_avx512bw));
 
     settings.add_preset("baseline", preset!());
     let nehalem = settings.add_preset(
@@ -54,13 +85,23 @@ fn define_settings(_shared: &SettingGroup) -> SettingGroup {
     );
     let haswell = settings.add_preset(
         "haswell",
-        preset!(nehalem && has_bmi1 && has_bmi2 && has_lzcnt),
+        preset!(
+            nehalem && has_bmi1 && has_bmi2 && has_lzcnt && has_avx && has_avx2 && has_fma && has_f16c
+        ),
     );
     let broadwell = settings.add_preset("broadwell", preset!(haswell));
     let skylake = settings.add_preset("skylake", preset!(broadwell));
+    let cascadelake = settings.add_preset(
+        "cascadelake",
+        preset!(skylake && has_avx512f && has_avx512vl && has_avx512dq && has_avx512bw),
+    );
     let cannonlake = settings.add_preset("cannonlake", preset!(skylake));
-    settings.add_preset("icelake", preset!(cannonlake));
+    let icelake = settings.add_preset("icelake", preset!(cannonlake));
     settings.add_preset(
+        "sapphirerapids",
+        preset!(icelake && has_avx512f && has_avx512vl && has_avx512dq && has_avx512bw),
+    );
+    let znver1 = settings.add_preset(
         "znver1",
         preset!(
             has_sse3
@@ -73,6 +114,11 @@ fn define_settings(_shared: &SettingGroup) -> SettingGroup {
                 && has_lzcnt
         ),
     );
+    let znver2 = settings.add_preset(
+        "znver2",
+        preset!(znver1 && has_avx && has_avx2 && has_fma && has_f16c),
+    );
+    settings.add_preset("znver3", preset!(znver2));
 
     settings.finish()
 }
@@ -86,11 +132,17 @@ fn define_registers() -> IsaRegs {
         .track_pressure(true);
     let int_regs = regs.add_bank(builder);
 
-    let builder = RegBankBuilder::new("FloatRegs", "xmm")
-        .units(16)
+    // 32 units to cover zmm0-zmm31; xmm/ymm are just the low bits of each zmm.
+    let builder = RegBankBuilder::new("FloatRegs", "zmm")
+        .units(32)
         .track_pressure(true);
     let float_regs = regs.add_bank(builder);
 
+    let builder = RegBankBuilder::new("MaskRegs", "k")
+        .units(8)
+        .track_pressure(false);
+    let mask_regs = regs.add_bank(builder);
+
     let builder = RegBankBuilder::new("FlagRegs", "")
         .units(1)
         .names(vec!["rflags"])
@@ -103,6 +155,9 @@ fn define_registers() -> IsaRegs {
     let builder = RegClassBuilder::new_toplevel("FPR", float_regs);
     let fpr = regs.add_class(builder);
 
+    let builder = RegClassBuilder::new_toplevel("K", mask_regs);
+    regs.add_class(builder);
+
     let builder = RegClassBuilder::new_toplevel("FLAG", flag_reg);
     regs.add_class(builder);
 
@@ -131,14 +186,17 @@ pub fn define(shared_defs: &mut SharedDefinitions) -> TargetIsa {
 
     let expand_flags = shared_defs.transform_groups.by_name("expand_flags");
     let narrow = shared_defs.transform_groups.by_name("narrow");
-    let widen = shared_defs.transform_groups.by_name("widen");
+    // `x86_narrow` fixes up the handful of instructions (`popcnt`, `rotl`,
+    // `rotr`) for which the shared `widen` group's zero/sign-extend-and-
+    // truncate approach is unsound, falling through to `widen` otherwise.
+    let x86_narrow = shared_defs.transform_groups.by_name("x86_narrow");
     let x86_expand = shared_defs.transform_groups.by_name("x86_expand");
 
     x86_32.legalize_monomorphic(expand_flags);
     x86_32.legalize_default(narrow);
     x86_32.legalize_type(B1, expand_flags);
-    x86_32.legalize_type(I8, widen);
-    x86_32.legalize_type(I16, widen);
+    x86_32.legalize_type(I8, x86_narrow);
+    x86_32.legalize_type(I16, x86_narrow);
     x86_32.legalize_type(I32, x86_expand);
     x86_32.legalize_type(F32, x86_expand);
     x86_32.legalize_type(F64, x86_expand);
@@ -146,8 +204,8 @@ pub fn define(shared_defs: &mut SharedDefinitions) -> TargetIsa {
     x86_64.legalize_monomorphic(expand_flags);
     x86_64.legalize_default(narrow);
     x86_64.legalize_type(B1, expand_flags);
-    x86_64.legalize_type(I8, widen);
-    x86_64.legalize_type(I16, widen);
+    x86_64.legalize_type(I8, x86_narrow);
+    x86_64.legalize_type(I16, x86_narrow);
     x86_64.legalize_type(I32, x86_expand);
     x86_64.legalize_type(I64, x86_expand);
     x86_64.legalize_type(F32, x86_expand);